@@ -0,0 +1,165 @@
+use crate::error::{decode_column, AppError};
+use crate::pool::ConnectionPool;
+use crate::roles;
+use crate::router::{self, Request};
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use rand::Rng;
+use spin_sdk::pg::ParameterValue;
+
+const SESSION_TTL_SECONDS: i64 = 60 * 60 * 24;
+
+#[derive(Serialize, Deserialize)]
+struct RegisterRequest {
+    name: String,
+    email: String,
+    password: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LoginRequest {
+    email: String,
+    password: String,
+}
+
+#[derive(Serialize)]
+struct SessionResponse {
+    token: String,
+}
+
+fn hash_password(password: &str) -> Result<String, AppError> {
+    let salt = SaltString::generate(&mut rand::thread_rng());
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| AppError::BadRequest(format!("could not hash password: {}", e)))
+}
+
+fn verify_password(password: &str, hash: &str) -> Result<bool, AppError> {
+    let parsed_hash = PasswordHash::new(hash)
+        .map_err(|e| AppError::BadRequest(format!("malformed password hash: {}", e)))?;
+
+    Ok(Argon2::default()
+        .verify_password(password.as_bytes(), &parsed_hash)
+        .is_ok())
+}
+
+fn generate_session_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(48)
+        .map(char::from)
+        .collect()
+}
+
+pub fn handle_register_request(
+    req: &Request,
+    pool: &ConnectionPool,
+) -> Result<(u16, String), AppError> {
+    let payload: RegisterRequest = router::body_json(req)?;
+    let password_hash = hash_password(&payload.password)?;
+    let client = pool.get()?;
+
+    let rowset = client
+        .query(
+            "INSERT INTO users (name, email, password) VALUES ($1, $2, $3) RETURNING id",
+            &[
+                ParameterValue::Str(payload.name),
+                ParameterValue::Str(payload.email),
+                ParameterValue::Str(password_hash),
+            ],
+        )
+        .map_err(conflict_on_duplicate_email)?;
+
+    let row = rowset.rows.get(0).ok_or(AppError::NotFound)?;
+    let user_id: i32 = decode_column(row, 0)?;
+
+    // The very first account has nobody to grant it admin, so it bootstraps
+    // itself; every account after that is provisioned via /roles/assign.
+    roles::bootstrap_admin_if_first_user(pool, user_id)?;
+
+    Ok((200, "User registered".to_string()))
+}
+
+pub fn handle_login_request(
+    req: &Request,
+    pool: &ConnectionPool,
+) -> Result<(u16, String), AppError> {
+    let payload: LoginRequest = router::body_json(req)?;
+    let mut client = pool.get()?;
+
+    let rowset = client.query(
+        "SELECT id, password FROM users WHERE email = $1",
+        &[ParameterValue::Str(payload.email)],
+    )?;
+
+    let row = rowset.rows.get(0).ok_or(AppError::Unauthorized)?;
+    let user_id: i32 = decode_column(row, 0)?;
+    let password_hash: String = decode_column(row, 1)?;
+
+    if !verify_password(&payload.password, &password_hash)? {
+        return Err(AppError::Unauthorized);
+    }
+
+    let token = generate_session_token();
+    client.execute(
+        "INSERT INTO sessions (token, user_id, expires_at) VALUES ($1, $2, NOW() + INTERVAL '1 second' * $3)",
+        &[
+            ParameterValue::Str(token.clone()),
+            ParameterValue::Int32(user_id),
+            ParameterValue::Int32(SESSION_TTL_SECONDS as i32),
+        ],
+    )?;
+
+    let body = serde_json::to_string(&SessionResponse { token })?;
+    Ok((200, body))
+}
+
+pub fn handle_logout_request(
+    req: &Request,
+    pool: &ConnectionPool,
+) -> Result<(u16, String), AppError> {
+    let token = bearer_token(req).ok_or(AppError::Unauthorized)?;
+    let mut client = pool.get()?;
+
+    client.execute(
+        "DELETE FROM sessions WHERE token = $1",
+        &[ParameterValue::Str(token)],
+    )?;
+
+    Ok((200, "Logged out".to_string()))
+}
+
+/// Resolves the `Authorization: Bearer <token>` header on `req` to the id of
+/// the user owning a live (non-expired) session, rejecting everything else
+/// with `AppError::Unauthorized`.
+pub fn authenticate(req: &Request, pool: &ConnectionPool) -> Result<i32, AppError> {
+    let token = bearer_token(req).ok_or(AppError::Unauthorized)?;
+    let client = pool.get()?;
+
+    let rowset = client.query(
+        "SELECT user_id FROM sessions WHERE token = $1 AND expires_at > NOW()",
+        &[ParameterValue::Str(token)],
+    )?;
+
+    let row = rowset.rows.get(0).ok_or(AppError::Unauthorized)?;
+    decode_column(row, 0)
+}
+
+fn bearer_token(req: &Request) -> Option<String> {
+    req.header("authorization")
+        .and_then(|value| value.as_str())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string())
+}
+
+fn conflict_on_duplicate_email(err: spin_sdk::pg::Error) -> AppError {
+    let message = err.to_string();
+    if message.contains("duplicate key") || message.contains("unique constraint") {
+        AppError::Conflict("email already registered".to_string())
+    } else {
+        AppError::Database(err)
+    }
+}
@@ -0,0 +1,59 @@
+use spin_sdk::pg::{Decode, Value};
+use thiserror::Error;
+
+/// Crate-wide error type returned by the `handle_*_request` functions.
+///
+/// Keeping this separate from `anyhow::Error` lets `error_to_response` match
+/// on specific failure modes (missing row, unique-constraint violation, ...)
+/// instead of panicking the connection thread on every Postgres error.
+#[derive(Debug, Error)]
+pub enum AppError {
+    #[error("not found")]
+    NotFound,
+
+    #[error("bad request: {0}")]
+    BadRequest(String),
+
+    #[error("conflict: {0}")]
+    Conflict(String),
+
+    #[error("unauthorized")]
+    Unauthorized,
+
+    #[error("forbidden")]
+    Forbidden,
+
+    #[error("database error: {0}")]
+    Database(#[from] spin_sdk::pg::Error),
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(err: serde_json::Error) -> Self {
+        AppError::BadRequest(err.to_string())
+    }
+}
+
+/// Decodes column `idx` of a query result row as `T`, turning a short row or
+/// a shape mismatch into a clean `AppError` response instead of panicking
+/// the component on an `unwrap()` or an out-of-bounds index.
+pub fn decode_column<T: Decode>(row: &[Value], idx: usize) -> Result<T, AppError> {
+    let value = row
+        .get(idx)
+        .ok_or_else(|| AppError::BadRequest(format!("missing column {}", idx)))?;
+
+    T::decode(value).map_err(|_| AppError::BadRequest(format!("could not decode column {}", idx)))
+}
+
+/// Maps an `AppError` to the HTTP status code and body the handlers return.
+pub fn error_to_response(err: AppError) -> (u16, String) {
+    let status = match &err {
+        AppError::NotFound => 404,
+        AppError::BadRequest(_) => 400,
+        AppError::Conflict(_) => 409,
+        AppError::Unauthorized => 401,
+        AppError::Forbidden => 403,
+        AppError::Database(_) => 500,
+    };
+
+    (status, err.to_string())
+}
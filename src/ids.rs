@@ -0,0 +1,57 @@
+use crate::error::AppError;
+use sqids::Sqids;
+use std::sync::OnceLock;
+
+/// Alphabet the encoder shuffles its short ids from. Swapping this (and/or
+/// `MIN_LENGTH`) changes every id this service has ever handed out, so treat
+/// it like a secret, not a cosmetic constant. Must be 62 distinct characters
+/// — `Sqids::builder().build()` rejects a non-unique alphabet.
+const ID_ALPHABET: &str = "WCqQkgbitc09OhfT2F8HsuvPRY57e3xU1LzZmw4Sr6MGdIpjVEolNaKBAnJyXD";
+const MIN_LENGTH: u8 = 6;
+
+fn sqids() -> &'static Sqids {
+    static SQIDS: OnceLock<Sqids> = OnceLock::new();
+    SQIDS.get_or_init(|| {
+        Sqids::builder()
+            .alphabet(ID_ALPHABET.chars().collect())
+            .min_length(MIN_LENGTH)
+            .build()
+            .expect("static alphabet/min_length are always valid")
+    })
+}
+
+/// Encodes a database primary key into the short, non-sequential token
+/// exposed in URLs and JSON payloads.
+pub fn encode_id(id: i32) -> String {
+    sqids()
+        .encode(&[id as u64])
+        .expect("a single u64 always encodes")
+}
+
+/// Reverses `encode_id`, rejecting anything that doesn't decode back to
+/// exactly one value.
+pub fn decode_id(encoded: &str) -> Result<i32, AppError> {
+    let values = sqids()
+        .decode(encoded);
+
+    match values.as_slice() {
+        [value] => i32::try_from(*value).map_err(|_| AppError::BadRequest("invalid id".to_string())),
+        _ => Err(AppError::BadRequest("invalid id".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_an_id() {
+        let encoded = encode_id(42);
+        assert_eq!(decode_id(&encoded).unwrap(), 42);
+    }
+
+    #[test]
+    fn rejects_garbage_input() {
+        assert!(decode_id("not-a-real-id").is_err());
+    }
+}
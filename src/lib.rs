@@ -1,179 +1,186 @@
 #![allow(dead_code)]
 use anyhow::Result;
-use http::{Request, Response};
-use spin_sdk::{
-    http_component,
-    pg::{self, Decode},
-};
-use std::io::{Read, Write};
-use std::net::{TcpListener, TcpStream};
+use spin_sdk::http::{IntoResponse, Response};
+use spin_sdk::http_component;
 
 #[macro_use]
 extern crate serde_derive;
 
+mod auth;
+mod error;
+mod ids;
+mod middleware;
+mod pool;
+mod roles;
+mod router;
+
+use error::{decode_column, error_to_response, AppError};
+use middleware::MiddlewareStack;
+use pool::ConnectionPool;
+use std::sync::OnceLock;
+
 const DB_URL_ENV: &str = "DB_URL";
 
+static POOL: OnceLock<ConnectionPool> = OnceLock::new();
+
+/// Returns the process-lifetime connection pool, opening it and running
+/// `set_database` exactly once on first use instead of per request. If setup
+/// fails the pool stays uninitialized so the next request retries it.
+fn get_pool() -> Result<&'static ConnectionPool> {
+    if let Some(pool) = POOL.get() {
+        return Ok(pool);
+    }
+
+    let pool = ConnectionPool::new(DB_URL_ENV);
+    set_database(&pool)?;
+    let _ = POOL.set(pool);
+    Ok(POOL.get().expect("just initialized above"))
+}
+
 #[derive(Serialize, Deserialize)]
 struct User {
-    id: Option<i32>,
+    /// The sqids-encoded short id, not the raw `SERIAL` primary key.
+    id: Option<String>,
     name: String,
     email: String,
 }
 
-const OK_RESPONSE: &str = "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n";
-const NOT_FOUND: &str = "HTTP/1.1 404 NOT FOUND\r\n\r\n";
-const INTERNAL_SERVER_ERROR: &str = "HTTP/1.1 500 INTERNAL SERVER ERROR\r\n\r\n";
-
-fn main() {
-    if let Err(e) = set_database() {
-        eprintln!("Error setting up database: {}", e);
-        return;
-    }
-
-    let listener = TcpListener::bind(format!("0.0.0.0:8080")).unwrap();
-    println!("Server started at port 8080");
-
-    //handle the client
-    for stream in listener.incoming() {
-        match stream {
-            Ok(stream) => {
-                handle_client(stream);
-            }
-            Err(e) => {
-                println!("Error: {}", e);
-            }
+/// Entry point for the Spin HTTP component. Every inbound request goes
+/// through `router::route`, which maps method + path segments to a handler
+/// instead of the old `TcpListener` loop's `request.starts_with(...)` checks.
+#[http_component]
+async fn handle_request(req: router::Request) -> Result<impl IntoResponse> {
+    let pool = match get_pool() {
+        Ok(pool) => pool,
+        Err(e) => {
+            eprintln!("Error setting up database: {}", e);
+            return Ok(json_response(500, "Error setting up database".to_string()));
         }
-    }
-}
-
-fn handle_client(mut stream: TcpStream) {
-    let mut buffer = [0; 1024];
-    let mut request = String::new();
+    };
 
-    match stream.read(&mut buffer) {
-        Ok(size) => {
-            request.push_str(String::from_utf8_lossy(&buffer[..size]).as_ref());
+    let middleware = MiddlewareStack::new()
+        .with_pre_filter(middleware::content_type_guard())
+        .with_post_hook(middleware::access_logger());
 
-            let (status_line, content) = match &*request {
-                r if r.starts_with("POST /users") => handle_post_request(r),
-                r if r.starts_with("PUT /users/") => handle_put_request(r),
-                r if r.starts_with("DELETE /users/") => handle_delete_request(r),
-                _ => (NOT_FOUND.to_string(), "404 Not Found".to_string()),
-            };
+    let (status, body) = middleware.run(&req, |r| {
+        router::route(r, pool).unwrap_or_else(error_to_response)
+    });
 
-            stream
-                .write_all(format!("{}{}", status_line, content).as_bytes())
-                .unwrap();
-        }
-        Err(e) => {
-            println!("Error: {}", e);
-        }
-    }
+    Ok(json_response(status, body))
 }
 
-fn handle_post_request(request: &str) -> (String, String) {
-    match (
-        get_user_request_body(&request),
-        pg::Connection::open(DB_URL_ENV),
-    ) {
-        (Ok(user), Ok(mut client)) => {
-            // Convert &String to String
-            let name = user.name.clone();
-            let email = user.email.clone();
-
-            client
-                .execute(
-                    "INSERT INTO users (name, email) VALUES ($1, $2)",
-                    &[spin_sdk::pg::ParameterValue::Str(name.clone()), spin_sdk::pg::ParameterValue::Str(email.clone())],  
-                )
-                .unwrap();
-
-            (OK_RESPONSE.to_string(), "User created".to_string())
-        }
-        _ => (INTERNAL_SERVER_ERROR.to_string(), "Error".to_string()),
-    }
+fn json_response(status: u16, body: String) -> Response {
+    Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(body)
+        .build()
 }
 
+fn handle_get_all_request(pool: &ConnectionPool) -> Result<(u16, String), AppError> {
+    let client = pool.get()?;
+    let rowset = client.query("SELECT id, name, email FROM users", &[])?;
+
+    let users = rowset
+        .rows
+        .iter()
+        .map(|row| {
+            Ok(User {
+                id: Some(ids::encode_id(decode_column(row, 0)?)),
+                name: decode_column(row, 1)?,
+                email: decode_column(row, 2)?,
+            })
+        })
+        .collect::<Result<Vec<User>, AppError>>()?;
+
+    Ok((200, serde_json::to_string(&users)?))
+}
 
+fn handle_get_one_request(id: i32, pool: &ConnectionPool) -> Result<(u16, String), AppError> {
+    let client = pool.get()?;
 
+    let rowset = client.query(
+        "SELECT id, name, email FROM users WHERE id = $1",
+        &[spin_sdk::pg::ParameterValue::Int32(id)],
+    )?;
 
+    let row = rowset.rows.get(0).ok_or(AppError::NotFound)?;
+    let user = User {
+        id: Some(ids::encode_id(decode_column(row, 0)?)),
+        name: decode_column(row, 1)?,
+        email: decode_column(row, 2)?,
+    };
 
-fn handle_put_request(request: &str) -> (String, String) {
-    match (
-        get_id(&request).parse::<i32>(),
-        get_user_request_body(&request),
-        pg::Connection::open(DB_URL_ENV),
-    ) {
-        (Ok(id), Ok(user), Ok(mut client)) => {
-            let name = user.name.clone();
-            let email = user.email.clone();
-
-            client
-                .execute(
-                    "UPDATE users SET name = $1, email = $2 WHERE id = $3",
-                    &[
-                        spin_sdk::pg::ParameterValue::Str(name.clone()),
-                        spin_sdk::pg::ParameterValue::Str(email.clone()),
-                        spin_sdk::pg::ParameterValue::Int32(id),
-                    ],
-                )
-                .unwrap();
+    Ok((200, serde_json::to_string(&user)?))
+}
 
-            (OK_RESPONSE.to_string(), "User updated".to_string())
-        }
-        _ => (INTERNAL_SERVER_ERROR.to_string(), "Error".to_string()),
+fn handle_put_request(
+    req: &router::Request,
+    id: i32,
+    pool: &ConnectionPool,
+) -> Result<(u16, String), AppError> {
+    let user: User = router::body_json(req)?;
+    let mut client = pool.get()?;
+
+    let rows_affected = client.execute(
+        "UPDATE users SET name = $1, email = $2 WHERE id = $3",
+        &[
+            spin_sdk::pg::ParameterValue::Str(user.name),
+            spin_sdk::pg::ParameterValue::Str(user.email),
+            spin_sdk::pg::ParameterValue::Int32(id),
+        ],
+    )?;
+
+    if rows_affected == 0 {
+        return Err(AppError::NotFound);
     }
-}
 
+    Ok((200, "User updated".to_string()))
+}
 
-fn handle_delete_request(request: &str) -> (String, String) {
-    match (
-        get_id(&request).parse::<i32>(),
-        pg::Connection::open(DB_URL_ENV),
-    ) {
-        (Ok(id), Ok(mut client)) => {
-            let rows_affected = client
-                .execute("DELETE FROM users WHERE id = $1", &[spin_sdk::pg::ParameterValue::Int32(id)])
-                .unwrap();
+fn handle_delete_request(id: i32, pool: &ConnectionPool) -> Result<(u16, String), AppError> {
+    let mut client = pool.get()?;
 
-            if rows_affected == 0 {
-                return (NOT_FOUND.to_string(), "User not found".to_string());
-            }
+    let rows_affected = client.execute(
+        "DELETE FROM users WHERE id = $1",
+        &[spin_sdk::pg::ParameterValue::Int32(id)],
+    )?;
 
-            (OK_RESPONSE.to_string(), "User deleted".to_string())
-        }
-        _ => (INTERNAL_SERVER_ERROR.to_string(), "Error".to_string()),
+    if rows_affected == 0 {
+        return Err(AppError::NotFound);
     }
+
+    Ok((200, "User deleted".to_string()))
 }
 
-fn set_database() -> Result<()> {
+fn set_database(pool: &ConnectionPool) -> Result<()> {
     // Connect to the database
-    let mut client = pg::Connection::open(DB_URL_ENV)?;
+    let mut client = pool.get()?;
 
     // SQL query to create the users table if it doesn't exist
     let sql = "CREATE TABLE IF NOT EXISTS users (
         id SERIAL PRIMARY KEY,
         name VARCHAR NOT NULL,
-        email VARCHAR NOT NULL
+        email VARCHAR NOT NULL UNIQUE,
+        password TEXT NOT NULL
     )";
 
     // Execute the SQL query to create the table
     client.execute(sql, &[])?;
 
+    // Sessions issued on login; deleted on logout. Expired rows are rejected
+    // by `auth::authenticate` but are not removed.
+    let sessions_sql = "CREATE TABLE IF NOT EXISTS sessions (
+        token TEXT PRIMARY KEY,
+        user_id INT NOT NULL REFERENCES users(id),
+        expires_at TIMESTAMP NOT NULL
+    )";
+
+    client.execute(sessions_sql, &[])?;
+
+    roles::set_up_roles(&mut *client)?;
+
     // Database setup successful
     Ok(())
 }
 
-fn get_id(request: &str) -> &str {
-    request
-        .split("/")
-        .nth(2)
-        .unwrap_or_default()
-        .split_whitespace()
-        .next()
-        .unwrap_or_default()
-}
-
-fn get_user_request_body(request: &str) -> Result<User, serde_json::Error> {
-    serde_json::from_str(request.split("\r\n\r\n").last().unwrap_or_default())
-}
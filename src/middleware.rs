@@ -0,0 +1,87 @@
+use crate::router::Request;
+use spin_sdk::http::Method;
+use std::time::{Duration, Instant};
+
+/// Runs before the handler; returning `Some` short-circuits the request with
+/// that status/body instead of reaching the router.
+pub type PreFilter = Box<dyn Fn(&Request) -> Option<(u16, String)> + Send + Sync>;
+/// Runs after the handler (or a short-circuiting `PreFilter`) with the final
+/// status and how long the request took.
+pub type PostHook = Box<dyn Fn(&Request, u16, Duration) + Send + Sync>;
+
+/// A small ordered chain of request filters and post-response hooks wrapping
+/// the router — in the spirit of Actix's `Transform`/`Service` pattern, but
+/// without the trait machinery. Additional filters (rate limiting, auth, ...)
+/// can be registered here without touching any handler.
+#[derive(Default)]
+pub struct MiddlewareStack {
+    pre_filters: Vec<PreFilter>,
+    post_hooks: Vec<PostHook>,
+}
+
+impl MiddlewareStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_pre_filter(mut self, filter: PreFilter) -> Self {
+        self.pre_filters.push(filter);
+        self
+    }
+
+    pub fn with_post_hook(mut self, hook: PostHook) -> Self {
+        self.post_hooks.push(hook);
+        self
+    }
+
+    /// Runs the pre-filters in order, falling through to `handler` if none of
+    /// them short-circuit, then runs every post-hook on the final response.
+    pub fn run(&self, req: &Request, handler: impl FnOnce(&Request) -> (u16, String)) -> (u16, String) {
+        let start = Instant::now();
+
+        let (status, body) = self
+            .pre_filters
+            .iter()
+            .find_map(|filter| filter(req))
+            .unwrap_or_else(|| handler(req));
+
+        let elapsed = start.elapsed();
+        for hook in &self.post_hooks {
+            hook(req, status, elapsed);
+        }
+
+        (status, body)
+    }
+}
+
+/// Rejects `POST`/`PUT` requests that don't declare `Content-Type:
+/// application/json`.
+pub fn content_type_guard() -> PreFilter {
+    Box::new(|req| {
+        if !matches!(req.method(), Method::Post | Method::Put) {
+            return None;
+        }
+
+        let is_json = req
+            .header("content-type")
+            .and_then(|value| value.as_str())
+            .map(|value| value.starts_with("application/json"))
+            .unwrap_or(false);
+
+        if is_json {
+            None
+        } else {
+            Some((
+                415,
+                "Unsupported Media Type: expected application/json".to_string(),
+            ))
+        }
+    })
+}
+
+/// Logs method, path, status, and elapsed time for every request.
+pub fn access_logger() -> PostHook {
+    Box::new(|req, status, elapsed| {
+        println!("{} {} -> {} ({:?})", req.method(), req.path(), status, elapsed);
+    })
+}
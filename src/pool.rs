@@ -0,0 +1,118 @@
+use crate::error::AppError;
+use spin_sdk::pg;
+use std::cell::Cell;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+
+/// A small pool of reusable `pg::Connection`s.
+///
+/// Opening a connection per request is wasteful, so handlers borrow one from
+/// here instead. Connections are returned to the pool automatically when the
+/// `ManagedConnection` guard drops.
+#[derive(Clone)]
+pub struct ConnectionPool {
+    url_env: &'static str,
+    connections: Arc<Mutex<Vec<pg::Connection>>>,
+}
+
+impl ConnectionPool {
+    /// Creates an empty pool against the database referenced by `url_env`.
+    /// Connections are opened lazily on first `get()`.
+    pub fn new(url_env: &'static str) -> Self {
+        ConnectionPool {
+            url_env,
+            connections: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Hands out a pooled connection, opening a new one if the pool is
+    /// empty. Idle connections aren't probed on checkout — that would cost a
+    /// round trip on every `get()` — so a connection that died while idle
+    /// surfaces as a query error on whoever checks it out next. That checkout
+    /// is poisoned by the failed query and isn't returned to the pool (see
+    /// `ManagedConnection::query`/`execute`), so the next `get()` opens a
+    /// fresh connection instead of handing back the same dead one.
+    pub fn get(&self) -> Result<ManagedConnection, AppError> {
+        let mut idle = self.connections.lock().unwrap();
+
+        let conn = match idle.pop() {
+            Some(conn) => conn,
+            None => pg::Connection::open(self.url_env)?,
+        };
+
+        Ok(ManagedConnection {
+            conn: Some(conn),
+            poisoned: Cell::new(false),
+            pool: self.connections.clone(),
+        })
+    }
+}
+
+/// A connection checked out from a `ConnectionPool`. Returns the connection
+/// to the pool on `Drop` so the next `get()` can reuse it — unless a query
+/// on it failed, in which case it's dropped instead so a connection that
+/// died while idle doesn't keep getting handed back out by `get()`'s LIFO
+/// `pop()`.
+pub struct ManagedConnection {
+    conn: Option<pg::Connection>,
+    poisoned: Cell<bool>,
+    pool: Arc<Mutex<Vec<pg::Connection>>>,
+}
+
+impl ManagedConnection {
+    /// Shadows `pg::Connection::query` so a failed query marks this
+    /// connection poisoned instead of letting it flow back to the pool via
+    /// plain `Deref`.
+    pub fn query(
+        &self,
+        statement: &str,
+        params: &[pg::ParameterValue],
+    ) -> Result<pg::RowSet, pg::Error> {
+        let result = self.conn.as_ref().unwrap().query(statement, params);
+        if result.is_err() {
+            self.poisoned.set(true);
+        }
+        result
+    }
+
+    /// Shadows `pg::Connection::execute` so a failed execute marks this
+    /// connection poisoned instead of letting it flow back to the pool via
+    /// plain `Deref`.
+    pub fn execute(
+        &mut self,
+        statement: &str,
+        params: &[pg::ParameterValue],
+    ) -> Result<u64, pg::Error> {
+        let result = self.conn.as_mut().unwrap().execute(statement, params);
+        if result.is_err() {
+            self.poisoned.set(true);
+        }
+        result
+    }
+}
+
+impl Deref for ManagedConnection {
+    type Target = pg::Connection;
+
+    fn deref(&self) -> &Self::Target {
+        self.conn.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for ManagedConnection {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.conn.as_mut().unwrap()
+    }
+}
+
+impl Drop for ManagedConnection {
+    fn drop(&mut self) {
+        if self.poisoned.get() {
+            return;
+        }
+
+        if let Some(conn) = self.conn.take() {
+            self.pool.lock().unwrap().push(conn);
+        }
+    }
+}
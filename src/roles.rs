@@ -0,0 +1,184 @@
+use crate::error::{decode_column, AppError};
+use crate::ids;
+use crate::pool::ConnectionPool;
+use crate::router::{self, Request};
+use spin_sdk::pg::ParameterValue;
+
+/// Permission required to create, update, or delete users.
+pub const PERMISSION_USERS_WRITE: &str = "users.write";
+/// Permission required to assign roles to other users.
+pub const PERMISSION_ROLES_ASSIGN: &str = "roles.assign";
+
+const DEFAULT_ADMIN_ROLE: &str = "admin";
+
+#[derive(Deserialize)]
+struct AssignRoleRequest {
+    /// The sqids-encoded short id, not the raw `SERIAL` primary key.
+    user_id: String,
+    role: String,
+}
+
+/// Creates the role/permission tables (if missing) and seeds the default
+/// `admin` role with every known permission. Called once from `set_database`.
+pub fn set_up_roles(client: &mut spin_sdk::pg::Connection) -> Result<(), AppError> {
+    client.execute(
+        "CREATE TABLE IF NOT EXISTS roles (
+            id SERIAL PRIMARY KEY,
+            name VARCHAR NOT NULL UNIQUE
+        )",
+        &[],
+    )?;
+
+    client.execute(
+        "CREATE TABLE IF NOT EXISTS permissions (
+            id SERIAL PRIMARY KEY,
+            name VARCHAR NOT NULL UNIQUE,
+            description VARCHAR NOT NULL
+        )",
+        &[],
+    )?;
+
+    client.execute(
+        "CREATE TABLE IF NOT EXISTS role_permissions (
+            role_id INT NOT NULL REFERENCES roles(id),
+            permission_id INT NOT NULL REFERENCES permissions(id),
+            PRIMARY KEY (role_id, permission_id)
+        )",
+        &[],
+    )?;
+
+    client.execute(
+        "CREATE TABLE IF NOT EXISTS user_roles (
+            user_id INT NOT NULL REFERENCES users(id),
+            role_id INT NOT NULL REFERENCES roles(id),
+            PRIMARY KEY (user_id, role_id)
+        )",
+        &[],
+    )?;
+
+    client.execute(
+        "INSERT INTO roles (name) VALUES ($1) ON CONFLICT (name) DO NOTHING",
+        &[ParameterValue::Str(DEFAULT_ADMIN_ROLE.to_string())],
+    )?;
+
+    for permission in [PERMISSION_USERS_WRITE, PERMISSION_ROLES_ASSIGN] {
+        client.execute(
+            "INSERT INTO permissions (name, description) VALUES ($1, $2) ON CONFLICT (name) DO NOTHING",
+            &[
+                ParameterValue::Str(permission.to_string()),
+                ParameterValue::Str(format!("grants {}", permission)),
+            ],
+        )?;
+
+        client.execute(
+            "INSERT INTO role_permissions (role_id, permission_id)
+             SELECT r.id, p.id FROM roles r, permissions p
+             WHERE r.name = $1 AND p.name = $2
+             ON CONFLICT DO NOTHING",
+            &[
+                ParameterValue::Str(DEFAULT_ADMIN_ROLE.to_string()),
+                ParameterValue::Str(permission.to_string()),
+            ],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Returns whether `user_id` holds a role granting `permission_name`.
+pub fn has_permission(
+    pool: &ConnectionPool,
+    user_id: i32,
+    permission_name: &str,
+) -> Result<bool, AppError> {
+    let client = pool.get()?;
+
+    let rowset = client.query(
+        "SELECT 1 FROM user_roles ur
+         JOIN role_permissions rp ON rp.role_id = ur.role_id
+         JOIN permissions p ON p.id = rp.permission_id
+         WHERE ur.user_id = $1 AND p.name = $2",
+        &[
+            ParameterValue::Int32(user_id),
+            ParameterValue::Str(permission_name.to_string()),
+        ],
+    )?;
+
+    Ok(!rowset.rows.is_empty())
+}
+
+/// Requires that `user_id` holds `permission_name`, translating a missing
+/// grant into `AppError::Forbidden`.
+pub fn require_permission(
+    pool: &ConnectionPool,
+    user_id: i32,
+    permission_name: &str,
+) -> Result<(), AppError> {
+    if has_permission(pool, user_id, permission_name)? {
+        Ok(())
+    } else {
+        Err(AppError::Forbidden)
+    }
+}
+
+/// Grants `user_id` the `admin` role if they're the only user in the
+/// system, so a freshly seeded database isn't permanently locked out of its
+/// own `users.write`/`roles.assign`-gated endpoints. Every user after the
+/// first must be promoted via `handle_assign_role_request` by an existing
+/// admin.
+pub fn bootstrap_admin_if_first_user(pool: &ConnectionPool, user_id: i32) -> Result<(), AppError> {
+    let mut client = pool.get()?;
+
+    let rowset = client.query("SELECT COUNT(*) FROM users", &[])?;
+    let row = rowset.rows.get(0).ok_or(AppError::NotFound)?;
+    let user_count: i64 = decode_column(row, 0)?;
+
+    if user_count != 1 {
+        return Ok(());
+    }
+
+    client.execute(
+        "INSERT INTO user_roles (user_id, role_id)
+         SELECT $1, id FROM roles WHERE name = $2
+         ON CONFLICT DO NOTHING",
+        &[
+            ParameterValue::Int32(user_id),
+            ParameterValue::Str(DEFAULT_ADMIN_ROLE.to_string()),
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// Admin-only endpoint: assigns a named role to a user.
+/// Expects `{"user_id": "<encoded id>", "role": "<name>"}` in the request body.
+pub fn handle_assign_role_request(
+    req: &Request,
+    pool: &ConnectionPool,
+    caller_id: i32,
+) -> Result<(u16, String), AppError> {
+    require_permission(pool, caller_id, PERMISSION_ROLES_ASSIGN)?;
+
+    let payload: AssignRoleRequest = router::body_json(req)?;
+    let user_id = ids::decode_id(&payload.user_id)?;
+    let mut client = pool.get()?;
+
+    let rowset = client.query(
+        "SELECT id FROM roles WHERE name = $1",
+        &[ParameterValue::Str(payload.role.clone())],
+    )?;
+    let role_row = rowset.rows.get(0).ok_or_else(|| {
+        AppError::BadRequest(format!("unknown role: {}", payload.role))
+    })?;
+    let role_id: i32 = decode_column(role_row, 0)?;
+
+    client.execute(
+        "INSERT INTO user_roles (user_id, role_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+        &[
+            ParameterValue::Int32(user_id),
+            ParameterValue::Int32(role_id),
+        ],
+    )?;
+
+    Ok((200, "Role assigned".to_string()))
+}
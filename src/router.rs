@@ -0,0 +1,52 @@
+use crate::error::AppError;
+use crate::pool::ConnectionPool;
+use crate::roles::{self, require_permission, PERMISSION_USERS_WRITE};
+use crate::{auth, handle_delete_request, handle_get_all_request, handle_get_one_request, handle_put_request};
+use spin_sdk::http::Method;
+
+pub type Request = spin_sdk::http::Request;
+
+/// Dispatches an inbound request to its handler by HTTP method and path
+/// segment, replacing the old `request.starts_with("POST /users")` string
+/// matching over a hand-parsed TCP stream.
+pub fn route(req: &Request, pool: &ConnectionPool) -> Result<(u16, String), AppError> {
+    let path = req.path().trim_matches('/');
+    let segments: Vec<&str> = if path.is_empty() {
+        Vec::new()
+    } else {
+        path.split('/').collect()
+    };
+
+    match (req.method(), segments.as_slice()) {
+        (&Method::Post, ["register"]) => auth::handle_register_request(req, pool),
+        (&Method::Post, ["login"]) => auth::handle_login_request(req, pool),
+        (&Method::Post, ["logout"]) => auth::handle_logout_request(req, pool),
+        (&Method::Post, ["roles", "assign"]) => {
+            let caller_id = auth::authenticate(req, pool)?;
+            roles::handle_assign_role_request(req, pool, caller_id)
+        }
+        (&Method::Get, ["users"]) => handle_get_all_request(pool),
+        (&Method::Get, ["users", id]) => handle_get_one_request(decode_id(id)?, pool),
+        (&Method::Put, ["users", id]) => {
+            let caller_id = auth::authenticate(req, pool)?;
+            require_permission(pool, caller_id, PERMISSION_USERS_WRITE)?;
+            handle_put_request(req, decode_id(id)?, pool)
+        }
+        (&Method::Delete, ["users", id]) => {
+            let caller_id = auth::authenticate(req, pool)?;
+            require_permission(pool, caller_id, PERMISSION_USERS_WRITE)?;
+            handle_delete_request(decode_id(id)?, pool)
+        }
+        _ => Err(AppError::NotFound),
+    }
+}
+
+/// Deserializes the request body as JSON, in place of splitting the raw
+/// request text on `\r\n\r\n`.
+pub fn body_json<T: serde::de::DeserializeOwned>(req: &Request) -> Result<T, AppError> {
+    Ok(serde_json::from_slice(req.body())?)
+}
+
+fn decode_id(segment: &str) -> Result<i32, AppError> {
+    crate::ids::decode_id(segment)
+}